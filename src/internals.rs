@@ -1,10 +1,15 @@
 use core::ops::Range;
+use std::collections::{HashMap, VecDeque};
 use std::ffi::OsString;
+use std::io;
 use std::io::Read;
 
-use camino::Utf8Path;
+use camino::{Utf8Path, Utf8PathBuf};
 use codespan_reporting::diagnostic::{Diagnostic, Label};
+use dialoguer::Confirm;
+use filetime::FileTime;
 use ignore::Walk;
+use regex::Regex;
 use self_cell::self_cell;
 
 /// This type alias is required by the `self_cell` macro.
@@ -80,26 +85,33 @@ impl FileList {
         if buf.trim().is_empty() {
             Err((buf, FLParseError::EmptyDirectory))
         } else {
-            Ok(FileList::from_string(buf))
+            Ok(FileList::from_string(buf, '\n'))
         }
     }
 
-    pub fn parse_reader<T: Read>(mut reader: T) -> Result<Self, (String, FLParseError)> {
+    /// Parses a list of paths out of `reader`, one path per line. When
+    /// `null_separated` is set the list is split on `\0` instead, matching
+    /// `-0`/`--null` in `mmv` and `xargs` for filenames containing `\n`.
+    pub fn parse_reader<T: Read>(
+        mut reader: T,
+        null_separated: bool,
+    ) -> Result<Self, (String, FLParseError)> {
         let mut buf = String::new();
         reader
             .read_to_string(&mut buf)
             .expect("Non-Utf8 path not supported");
-        buf.truncate(buf.trim_end().len());
-        if buf.trim().is_empty() {
+        let separator = if null_separated { '\0' } else { '\n' };
+        buf.truncate(buf.trim_end_matches(separator).len());
+        if buf.is_empty() {
             Err((buf, FLParseError::EmptyStdIn))
         } else {
-            Ok(FileList::from_string(buf))
+            Ok(FileList::from_string(buf, separator))
         }
     }
 
-    fn from_string(string: String) -> Self {
+    fn from_string(string: String, separator: char) -> Self {
         FileList(SharedPaths::new(string, |s| {
-            s.lines().map(|s| Utf8Path::new(s)).collect()
+            s.split(separator).map(Utf8Path::new).collect()
         }))
     }
 
@@ -133,15 +145,32 @@ impl FileList {
         });
     }
 
-    pub fn as_string(&self) -> String {
+    pub fn as_string(&self, null_separated: bool) -> String {
+        let separator = if null_separated { '\0' } else { '\n' };
         let mut buf = String::new();
         for path in self.0.borrow_dependent() {
             buf.push_str(path.as_ref());
-            buf.push('\n');
+            buf.push(separator);
         }
         buf.pop();
         buf
     }
+
+    /// Builds the target list for a `--replace` invocation by applying
+    /// `pattern` (with capture-group substitution, e.g. `$1`) to every
+    /// occurrence in every path in this list. `null_separated` must match
+    /// whatever was passed to `parse_reader`/`as_string` for this list, so
+    /// that a path containing the separator character round-trips correctly.
+    pub fn substitute(&self, pattern: &Regex, replacement: &str, null_separated: bool) -> Self {
+        let separator = if null_separated { '\0' } else { '\n' };
+        let mut buf = String::new();
+        for path in self.0.borrow_dependent() {
+            buf.push_str(&pattern.replace_all(path.as_str(), replacement));
+            buf.push(separator);
+        }
+        buf.pop();
+        FileList::from_string(buf, separator)
+    }
 }
 
 impl AsRef<str> for FileList {
@@ -169,6 +198,22 @@ impl RenameRequest {
 
         match target_len.cmp(&origin_len) {
             Ordering::Equal => {
+                let origin_duplicates = find_duplicate_labels(&origin, origin_vec);
+                if !origin_duplicates.is_empty() {
+                    return Err((
+                        origin.into_owner(),
+                        RRParseError::DuplicateOrigin(origin_duplicates),
+                    ));
+                }
+
+                let target_duplicates = find_duplicate_labels(&target, target_vec);
+                if !target_duplicates.is_empty() {
+                    return Err((
+                        target.into_owner(),
+                        RRParseError::DuplicateTarget(target_duplicates),
+                    ));
+                }
+
                 if origin_vec
                     .iter()
                     .zip(target_vec.iter())
@@ -191,7 +236,7 @@ impl RenameRequest {
         }
     }
 
-    pub fn print_diffs(&self) {
+    pub fn print_diffs(&self, options: &RenameOptions) {
         use codespan_reporting::term::termcolor::ColorSpec as Spec;
         use codespan_reporting::term::termcolor::{BufferWriter, WriteColor};
         use codespan_reporting::term::termcolor::{Color, ColorChoice};
@@ -202,6 +247,13 @@ impl RenameRequest {
         let origin = self.origin.borrow_dependent();
         let target = self.target.borrow_dependent();
 
+        // Needed to tell a target that merely sits at another line's origin
+        // path (which `rename`'s ordering logic will vacate first, see
+        // RenameRequest::rename) apart from a target that genuinely collides
+        // with an unrelated, untouched file.
+        let origin_canon: Vec<Utf8PathBuf> = origin.iter().map(|p| canonical_origin(p)).collect();
+        let origin_index = build_origin_index(&origin_canon);
+
         let wtr = BufferWriter::stdout(ColorChoice::Always);
         let mut buf = wtr.buffer();
 
@@ -212,7 +264,7 @@ impl RenameRequest {
             };
         }
 
-        for (before, after) in origin.iter().zip(target) {
+        for (i, (before, after)) in origin.iter().zip(target).enumerate() {
             let chunk_vec = dissimilar::diff(before.as_ref(), after.as_ref());
 
             // The padding is calculated manually because ANSI escape codes interfere with
@@ -254,6 +306,20 @@ impl RenameRequest {
             }
             write_buf!(Spec::new(), "{}", padding);
             write_buf!(Spec::new().set_italic(true), "(rename)");
+            let vacated_by_another_line =
+                matches!(origin_index.get(canonical_target(after).as_path()), Some(&j) if j != i);
+            if after.exists() && !vacated_by_another_line {
+                write_buf!(
+                    Spec::new().set_fg(Some(Color::Red)).set_italic(true),
+                    " (overwrite)"
+                );
+            }
+            if options.create_parents && parent_needs_creating(after) {
+                write_buf!(
+                    Spec::new().set_fg(Some(Color::Yellow)).set_italic(true),
+                    " (new dir)"
+                );
+            }
             writeln!(&mut buf).unwrap();
         }
 
@@ -262,21 +328,327 @@ impl RenameRequest {
         wtr.print(&buf).unwrap();
     }
 
-    pub fn rename(self) -> Result<(), CannotRenameFile> {
+    pub fn rename(self, options: &RenameOptions) -> Result<(), CannotRenameFile> {
         let origin = self.origin.borrow_dependent();
         let target = self.target.borrow_dependent();
-        for (before, after) in origin.iter().zip(target) {
-            if let Err(e) = std::fs::rename(before, after) {
-                return Err(CannotRenameFile(
-                    (before.to_string(), after.to_string()),
-                    format!("{}", e),
-                ));
+        let len = origin.len();
+
+        // RATIONALE:
+        //   Renaming in input order corrupts chains (`a->b` then `b->c` clobbers the
+        //   original `b`) and swaps (`a<->b` destroys one file). Instead we compute a
+        //   dependency order: move `i` must run after move `j` whenever `target[i]`
+        //   occupies the spot that `origin[j]` currently vacates. Moves with no
+        //   dependencies run immediately; the rest are released as their dependencies
+        //   are satisfied (Kahn's algorithm). A cycle (e.g. `a<->b`, or longer rings)
+        //   has no move with indegree zero, so it is broken by diverting one member
+        //   through a fresh temporary name in its own directory, then finalizing that
+        //   temporary into its real target once the rest of the ring has moved.
+        let origin_canon: Vec<Utf8PathBuf> = origin.iter().map(|p| canonical_origin(p)).collect();
+        let target_canon: Vec<Utf8PathBuf> = target.iter().map(|p| canonical_target(p)).collect();
+        let origin_index = build_origin_index(&origin_canon);
+
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); len];
+        let mut indegree = vec![0usize; len];
+        for (i, path) in target_canon.iter().enumerate() {
+            if let Some(&j) = origin_index.get(path.as_path()) {
+                if j != i {
+                    dependents[j].push(i);
+                    indegree[i] += 1;
+                }
+            }
+        }
+
+        let mut moved = vec![false; len];
+        let mut ready: VecDeque<usize> = (0..len).filter(|&i| indegree[i] == 0).collect();
+        let mut finalize: Vec<(usize, Utf8PathBuf)> = Vec::new();
+        let mut remaining = len;
+
+        while remaining > 0 {
+            while let Some(i) = ready.pop_front() {
+                execute_move(origin[i], target[i], options)?;
+                moved[i] = true;
+                remaining -= 1;
+                for &dependent in &dependents[i] {
+                    indegree[dependent] -= 1;
+                    if indegree[dependent] == 0 && !moved[dependent] {
+                        ready.push_back(dependent);
+                    }
+                }
+            }
+
+            if remaining == 0 {
+                break;
+            }
+
+            // Every unmoved file has indegree > 0: we are stuck in a cycle. Break it
+            // by diverting one member through a temporary name in its own directory.
+            let cycle_member = (0..len)
+                .find(|&i| !moved[i])
+                .expect("remaining > 0 implies an unmoved file exists");
+            let temp = unique_temp_path(origin[cycle_member]);
+            do_rename(origin[cycle_member], &temp)?;
+            moved[cycle_member] = true;
+            remaining -= 1;
+            finalize.push((cycle_member, temp));
+            for &dependent in &dependents[cycle_member] {
+                indegree[dependent] -= 1;
+                // A dependent can itself be a diverted cycle member that is
+                // already `moved`, e.g. its own cycle partner releasing it
+                // after it was diverted; don't queue it a second time.
+                if indegree[dependent] == 0 && !moved[dependent] {
+                    ready.push_back(dependent);
+                }
             }
         }
+
+        for (i, temp) in finalize {
+            execute_move(&temp, target[i], options)?;
+        }
+
         Ok(())
     }
 }
 
+/// Labels every line in `lines` whose path also appears on another line
+/// (the first occurrence included), so that both `RenameRequest::new` checks (duplicate origins, duplicate
+/// targets) can share one implementation.
+fn find_duplicate_labels(paths: &SharedPaths, lines: &[&Utf8Path]) -> Vec<Label<()>> {
+    let mut first_seen: HashMap<&Utf8Path, usize> = HashMap::with_capacity(lines.len());
+    let mut duplicate_indices: Vec<usize> = Vec::new();
+    for (i, path) in lines.iter().enumerate() {
+        match first_seen.get(path) {
+            Some(&first) => {
+                if !duplicate_indices.contains(&first) {
+                    duplicate_indices.push(first);
+                }
+                duplicate_indices.push(i);
+            }
+            None => {
+                first_seen.insert(path, i);
+            }
+        }
+    }
+    duplicate_indices.sort_unstable();
+    duplicate_indices
+        .into_iter()
+        .map(|i| Label::primary((), paths.substring_range(lines[i])).with_message("duplicate path"))
+        .collect()
+}
+
+/// Maps each canonicalized origin path to its position in the batch, so that
+/// a target path can be checked for membership in the origin set.
+fn build_origin_index(origin_canon: &[Utf8PathBuf]) -> HashMap<&Utf8Path, usize> {
+    let mut origin_index = HashMap::with_capacity(origin_canon.len());
+    for (i, path) in origin_canon.iter().enumerate() {
+        origin_index.insert(path.as_path(), i);
+    }
+    origin_index
+}
+
+/// Canonical form of a file that is known to exist on disk.
+fn canonical_origin(path: &Utf8Path) -> Utf8PathBuf {
+    let canon =
+        std::fs::canonicalize(path).expect("TOCTTOU error: files are expected to exist");
+    Utf8PathBuf::from_path_buf(canon).expect("Non-Utf8 path not supported")
+}
+
+/// Canonical form of a rename target, which may not exist yet. Falls back to
+/// canonicalizing the parent directory and rejoining the file name, so that
+/// targets can still be compared against origins; where the parent does not
+/// exist either, the parent is used as given.
+fn canonical_target(path: &Utf8Path) -> Utf8PathBuf {
+    if let Ok(canon) = std::fs::canonicalize(path) {
+        return Utf8PathBuf::from_path_buf(canon).expect("Non-Utf8 path not supported");
+    }
+    let parent = path.parent().unwrap_or_else(|| Utf8Path::new("."));
+    let file_name = path.file_name().unwrap_or_default();
+    match std::fs::canonicalize(parent) {
+        Ok(canon_parent) => {
+            Utf8PathBuf::from_path_buf(canon_parent).expect("Non-Utf8 path not supported")
+        }
+        Err(_) => parent.to_path_buf(),
+    }
+    .join(file_name)
+}
+
+/// A unique path in the same directory as `origin`, used as an intermediary
+/// when breaking a rename cycle.
+fn unique_temp_path(origin: &Utf8Path) -> Utf8PathBuf {
+    let dir = origin.parent().unwrap_or_else(|| Utf8Path::new("."));
+    let file_name = origin.file_name().unwrap_or("mv-with-tmp");
+    for suffix in 0u64.. {
+        let candidate = dir.join(format!(".{}.mv-with-tmp-{}-{}", file_name, std::process::id(), suffix));
+        if !candidate.exists() {
+            return candidate;
+        }
+    }
+    unreachable!("directory cannot contain infinitely many candidate names")
+}
+
+/// How to handle a rename whose target already exists as an unrelated file,
+/// and whether to create a target's missing parent directories.
+/// Mirrors coreutils `mv -n`/`-i`/`-b`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RenameOptions {
+    /// `-n`/`--no-clobber`: skip renames whose target already exists
+    pub no_clobber: bool,
+    /// `-i`/`--interactive`: prompt before overwriting an existing target
+    pub interactive: bool,
+    /// `-b`/`--backup[=numbered]`: move an existing target aside first
+    pub backup: Option<BackupMode>,
+    /// `-p`/`--parents`: create a target's missing parent directories
+    pub create_parents: bool,
+}
+
+/// Whether `target`'s parent directory does not exist yet, and so would be
+/// created by `-p`/`--parents`.
+fn parent_needs_creating(target: &Utf8Path) -> bool {
+    match target.parent() {
+        Some(parent) if !parent.as_str().is_empty() => !parent.exists(),
+        _ => false,
+    }
+}
+
+/// The naming scheme used for `-b`/`--backup`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackupMode {
+    /// `name~`
+    Simple,
+    /// `name.~N~`, picking the lowest `N` not already taken
+    Numbered,
+}
+
+/// Moves `from` to `to`, honouring `options` when `to` already exists.
+fn execute_move(
+    from: &Utf8Path,
+    to: &Utf8Path,
+    options: &RenameOptions,
+) -> Result<(), CannotRenameFile> {
+    // An "(ignore)" line in `print_diffs` (`from == to`) must stay a no-op
+    // here too: `to.exists()` is trivially true for it, so without this guard
+    // `-b`/`-i` would treat the untouched file as an overwrite of itself,
+    // backing it up (or prompting to overwrite) and then renaming it away
+    // from under the `do_rename(from, to)` that follows.
+    if from == to {
+        return Ok(());
+    }
+    if to.exists() {
+        if options.no_clobber {
+            return Ok(());
+        }
+        if options.interactive
+            && !Confirm::new()
+                .with_prompt(format!("overwrite '{}'?", to))
+                .interact()
+                .unwrap_or(false)
+        {
+            return Ok(());
+        }
+        if let Some(mode) = options.backup {
+            do_rename(to, &backup_path(to, mode))?;
+        }
+    }
+    if options.create_parents && parent_needs_creating(to) {
+        std::fs::create_dir_all(to.parent().unwrap()).map_err(|e| {
+            CannotRenameFile((from.to_string(), to.to_string()), format!("{}", e), false)
+        })?;
+    }
+    do_rename(from, to)
+}
+
+fn backup_path(target: &Utf8Path, mode: BackupMode) -> Utf8PathBuf {
+    match mode {
+        BackupMode::Simple => Utf8PathBuf::from(format!("{}~", target)),
+        BackupMode::Numbered => {
+            let mut n = 1u64;
+            loop {
+                let candidate = Utf8PathBuf::from(format!("{}.~{}~", target, n));
+                if !candidate.exists() {
+                    return candidate;
+                }
+                n += 1;
+            }
+        }
+    }
+}
+
+fn do_rename(from: &Utf8Path, to: &Utf8Path) -> Result<(), CannotRenameFile> {
+    match std::fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        // `std::fs::rename` cannot move a file across filesystem boundaries
+        // (e.g. into a different mounted volume, or a tmpfs); fall back to
+        // copying the contents over and then removing the original, the way
+        // coreutils `mv` does.
+        Err(e) if is_cross_device(&e) => copy_then_remove(from, to).map_err(|e| {
+            CannotRenameFile((from.to_string(), to.to_string()), format!("{}", e), true)
+        }),
+        Err(e) => Err(CannotRenameFile(
+            (from.to_string(), to.to_string()),
+            format!("{}", e),
+            false,
+        )),
+    }
+}
+
+fn is_cross_device(err: &io::Error) -> bool {
+    // `ErrorKind::CrossesDevices` covers most platforms; the raw EXDEV check
+    // is kept as a fallback for targets where the OS error isn't classified.
+    err.kind() == io::ErrorKind::CrossesDevices || err.raw_os_error() == Some(18)
+}
+
+fn copy_then_remove(from: &Utf8Path, to: &Utf8Path) -> io::Result<()> {
+    // `symlink_metadata` (unlike `metadata`) does not follow a symlink, so we
+    // can tell a real symlink apart from the file/directory it points at, and
+    // also notice a *broken* symlink already sitting at `to` (whose `.exists()`
+    // would otherwise report `false`).
+    let metadata = std::fs::symlink_metadata(from)?;
+    let to_exists = std::fs::symlink_metadata(to).is_ok();
+
+    if metadata.is_symlink() {
+        // Recreate the symlink itself rather than dereferencing it: copying
+        // the pointed-to content (what `fs::copy` would do) silently turns a
+        // symlink into a plain file, and also breaks if the link's target is
+        // renamed away before this one is processed.
+        let link_target = std::fs::read_link(from)?;
+        // `std::fs::rename` overwrites an existing file/symlink at `to`
+        // transparently; match that instead of failing with EEXIST.
+        if to_exists {
+            std::fs::remove_file(to)?;
+        }
+        std::os::unix::fs::symlink(&link_target, to)?;
+        std::fs::remove_file(from)
+    } else if metadata.is_dir() {
+        // `std::fs::rename` fails (ENOTEMPTY/EEXIST) when `to` already exists;
+        // mirror that instead of silently merging `from`'s children into it.
+        if to_exists {
+            return Err(io::Error::from(io::ErrorKind::DirectoryNotEmpty));
+        }
+        std::fs::create_dir_all(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let name = entry?.file_name();
+            let from_child = from.as_std_path().join(&name);
+            let to_child = to.as_std_path().join(&name);
+            copy_then_remove(
+                Utf8Path::from_path(&from_child).expect("Non-Utf8 path not supported"),
+                Utf8Path::from_path(&to_child).expect("Non-Utf8 path not supported"),
+            )?;
+        }
+        std::fs::remove_dir(from)
+    } else {
+        // Same overwrite semantics as the symlink branch above: if `to` is a
+        // symlink, `fs::copy` would instead write through it to whatever it
+        // points at, rather than replacing it.
+        if to_exists {
+            std::fs::remove_file(to)?;
+        }
+        // `fs::copy` already preserves the source's permission bits.
+        std::fs::copy(from, to)?;
+        std::fs::File::open(to)?.sync_all()?;
+        filetime::set_file_mtime(to, FileTime::from_last_modification_time(&metadata))?;
+        std::fs::remove_file(from)
+    }
+}
+
 /// Error that is triggered when you misspell an argument
 /// ```bash
 /// $ mv-with ivm
@@ -290,14 +662,34 @@ impl<'a> MisspelledBashCommand<'a> {
     }
 }
 
-/// Error that is triggered when mv-with cannot rename a file
-pub struct CannotRenameFile(pub (String, String), pub String);
+/// Error that is triggered when the pattern passed to `--replace` is not a
+/// valid regex.
+/// ```bash
+/// $ echo foo | mv-with --replace '(' bar
+/// ```
+pub struct ReplacePatternError(pub regex::Error);
+impl ReplacePatternError {
+    pub fn report(&self) -> Diagnostic<()> {
+        Diagnostic::error()
+            .with_message("invalid --replace regex pattern")
+            .with_notes(vec![format!("{}", self.0)])
+    }
+}
+
+/// Error that is triggered when mv-with cannot rename a file. The final
+/// `bool` marks whether this happened while falling back to a cross-device
+/// copy, rather than a plain `std::fs::rename`.
+pub struct CannotRenameFile(pub (String, String), pub String, pub bool);
 impl CannotRenameFile {
     pub fn report(self) -> Diagnostic<()> {
         let (before, after) = self.0;
+        let mut notes = vec![format!("Underlying OS error: {}", self.1)];
+        if self.2 {
+            notes.push("this was a cross-device move, which mv-with handles by copying".into());
+        }
         Diagnostic::error()
             .with_message(format!("cannot rename `{}` to `{}`", before, after))
-            .with_notes(vec![format!("Underlying OS error: {}", self.1)])
+            .with_notes(notes)
     }
 }
 
@@ -354,6 +746,10 @@ pub enum RRParseError {
     TooManyLines(Range<usize>),
     /// Triggered if the file is unchanged
     FileUnchanged,
+    /// Triggered if two edited lines point at the same target path
+    DuplicateTarget(Vec<Label<()>>),
+    /// Triggered if two lines share the same origin path
+    DuplicateOrigin(Vec<Label<()>>),
 }
 
 use RRParseError::*;
@@ -363,6 +759,18 @@ impl RRParseError {
             FileUnchanged => {
                 return Diagnostic::note().with_message("Temporary file was unchanged")
             }
+            DuplicateTarget(labels) => {
+                return Diagnostic::error()
+                    .with_message("Multiple files would be renamed to the same target")
+                    .with_labels(labels)
+                    .with_notes(vec!["each line must produce a unique path".into()])
+            }
+            DuplicateOrigin(labels) => {
+                return Diagnostic::error()
+                    .with_message("The same file appears as the origin of multiple lines")
+                    .with_labels(labels)
+                    .with_notes(vec!["each line must start from a unique path".into()])
+            }
             TooFewLines(end) => Diagnostic::error()
                 .with_message("Unexpected EOF")
                 .with_labels(vec![
@@ -380,7 +788,90 @@ impl RRParseError {
     pub fn status(&self) -> Option<i32> {
         match self {
             FileUnchanged => Some(0),
-            TooFewLines(_) | TooManyLines(_) => Some(1),
+            TooFewLines(_) | TooManyLines(_) | DuplicateTarget(_) | DuplicateOrigin(_) => Some(1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// A fresh scratch directory for one test, already containing `names` as
+    /// files whose contents are their own name, so a test can tell after a
+    /// rename which original file ended up at which path.
+    fn scratch_dir(test_name: &str, names: &[&str]) -> Utf8PathBuf {
+        let dir = Utf8PathBuf::from_path_buf(std::env::temp_dir())
+            .unwrap()
+            .join(format!("mv-with-test-{}-{}", std::process::id(), test_name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for name in names {
+            fs::write(dir.join(name), name.as_bytes()).unwrap();
+        }
+        dir
+    }
+
+    fn file_list(dir: &Utf8Path, names: &[&str]) -> FileList {
+        let buf = names
+            .iter()
+            .map(|n| dir.join(n).to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        FileList::from_string(buf, '\n')
+    }
+
+    fn contents(path: &Utf8Path) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    fn rename(dir: &Utf8Path, origin: &[&str], target: &[&str]) {
+        let request = RenameRequest::new(file_list(dir, origin), file_list(dir, target))
+            .ok()
+            .expect("test batches must not trip duplicate/unchanged checks");
+        if request.rename(&RenameOptions::default()).is_err() {
+            panic!("test batch must not hit a filesystem error");
         }
     }
+
+    #[test]
+    fn chain_rename_does_not_clobber_the_middle_file() {
+        // a->b->c: naive in-order renaming would clobber `b`'s original
+        // contents before it gets moved on to `c`.
+        let dir = scratch_dir("chain", &["a", "b"]);
+        rename(&dir, &["a", "b"], &["b", "c"]);
+        assert_eq!(contents(&dir.join("b")), "a");
+        assert_eq!(contents(&dir.join("c")), "b");
+        assert!(!dir.join("a").exists());
+    }
+
+    #[test]
+    fn two_cycle_swap_preserves_both_files() {
+        let dir = scratch_dir("two-cycle", &["a", "b"]);
+        rename(&dir, &["a", "b"], &["b", "a"]);
+        assert_eq!(contents(&dir.join("a")), "b");
+        assert_eq!(contents(&dir.join("b")), "a");
+    }
+
+    #[test]
+    fn three_cycle_rotate_preserves_all_files() {
+        let dir = scratch_dir("three-cycle", &["a", "b", "c"]);
+        rename(&dir, &["a", "b", "c"], &["b", "c", "a"]);
+        assert_eq!(contents(&dir.join("a")), "c");
+        assert_eq!(contents(&dir.join("b")), "a");
+        assert_eq!(contents(&dir.join("c")), "b");
+    }
+
+    #[test]
+    fn disjoint_cycles_in_one_batch_both_resolve() {
+        // Two independent 2-cycles (`a<->b`, `x<->y`) in the same batch must
+        // not interfere with each other's cycle-breaking.
+        let dir = scratch_dir("disjoint-cycles", &["a", "b", "x", "y"]);
+        rename(&dir, &["a", "b", "x", "y"], &["b", "a", "y", "x"]);
+        assert_eq!(contents(&dir.join("a")), "b");
+        assert_eq!(contents(&dir.join("b")), "a");
+        assert_eq!(contents(&dir.join("x")), "y");
+        assert_eq!(contents(&dir.join("y")), "x");
+    }
 }
@@ -13,6 +13,7 @@ use codespan_reporting::term::Config;
 use dialoguer::Confirm;
 use ignore::WalkBuilder;
 use lazy_static::lazy_static;
+use regex::Regex;
 
 mod internals;
 use internals::*;
@@ -38,12 +39,61 @@ fn real_main() -> i32 {
         .arg(
             Arg::with_name("EDITOR")
                 .help("Sets the editor to use")
-                .required(true)
+                .required_unless("replace")
                 .index(1),
         )
+        .arg(
+            Arg::with_name("replace")
+                .long("replace")
+                .number_of_values(2)
+                .value_names(&["PATTERN", "REPLACEMENT"])
+                .help("Rename by regex find/replace instead of launching an editor, e.g. --replace 'foo(\\d+)' 'bar$1'"),
+        )
+        .arg(
+            Arg::with_name("null")
+                .short("0")
+                .long("null")
+                .help("Read and write paths separated by NUL instead of newline, for filenames containing newlines"),
+        )
+        .arg(
+            Arg::with_name("no-clobber")
+                .short("n")
+                .long("no-clobber")
+                .help("Never rename over an existing file"),
+        )
+        .arg(
+            Arg::with_name("interactive")
+                .short("i")
+                .long("interactive")
+                .help("Prompt before renaming over an existing file"),
+        )
+        .arg(
+            Arg::with_name("backup")
+                .short("b")
+                .long("backup")
+                .takes_value(true)
+                .min_values(0)
+                .possible_values(&["numbered"])
+                .help("Back up each existing destination file before overwriting it; --backup=numbered keeps one backup per generation"),
+        )
+        .arg(
+            Arg::with_name("parents")
+                .short("p")
+                .long("parents")
+                .help("Create missing parent directories of rename targets"),
+        )
         .get_matches();
 
-    let editor = matches.value_of("EDITOR").unwrap();
+    let null_separated = matches.is_present("null");
+    let rename_options = RenameOptions {
+        no_clobber: matches.is_present("no-clobber"),
+        interactive: matches.is_present("interactive"),
+        backup: matches.is_present("backup").then(|| match matches.value_of("backup") {
+            Some("numbered") => BackupMode::Numbered,
+            _ => BackupMode::Simple,
+        }),
+        create_parents: matches.is_present("parents"),
+    };
 
     let mut file_origins = {
         match {
@@ -54,7 +104,7 @@ fn real_main() -> i32 {
                         .build(),
                 )
             } else {
-                FileList::parse_reader(io::stdin().lock())
+                FileList::parse_reader(io::stdin().lock(), null_separated)
             }
             .map(|f| f.confirm_files_exist())
         } {
@@ -73,35 +123,53 @@ fn real_main() -> i32 {
     // Hence, file `foo/bar` is renamed before `foo`
     file_origins.sort_by_file_depth();
 
-    fs::write(TEMP_FILE, file_origins.as_string()).unwrap();
-
-    let command = format!("{} {}", &editor, TEMP_FILE);
-    let status = Command::new("/usr/bin/sh")
-        .arg("-c")
-        .arg(&command)
-        .spawn()
-        .expect("Failed to run bash")
-        .wait()
-        .unwrap();
-
-    match status.code() {
-        Some(127) => {
-            // Status 127 means that bash couldn't find the command; implies that
-            // the command was likely misspelt
-            let file = SimpleFile::new("", &command);
-            let diagnostic = &errors::MisspelledBashCommand(editor).report();
-            term::emit(&mut WRITER.lock(), &CONFIG, &file, diagnostic).unwrap();
-            return 1;
-        }
-        _ => {
-            if !status.success() {
-                panic!("Bash returned unsuccessful exit status: {:?}", status)
+    let file_targets = if let Some(mut replace) = matches.values_of("replace") {
+        let pattern_str = replace.next().unwrap();
+        let replacement = replace.next().unwrap();
+        let pattern = match Regex::new(pattern_str) {
+            Ok(pattern) => pattern,
+            Err(err) => {
+                // Error handling for an invalid --replace regex
+                let file = SimpleFile::new("", pattern_str);
+                let diagnostic = ReplacePatternError(err).report();
+                term::emit(&mut WRITER.lock(), &CONFIG, &file, &diagnostic).unwrap();
+                return 1;
+            }
+        };
+        file_origins.substitute(&pattern, replacement, null_separated)
+    } else {
+        let editor = matches.value_of("EDITOR").unwrap();
+
+        fs::write(TEMP_FILE, file_origins.as_string(null_separated)).unwrap();
+
+        let command = format!("{} {}", &editor, TEMP_FILE);
+        let status = Command::new("/usr/bin/sh")
+            .arg("-c")
+            .arg(&command)
+            .spawn()
+            .expect("Failed to run bash")
+            .wait()
+            .unwrap();
+
+        match status.code() {
+            Some(127) => {
+                // Status 127 means that bash couldn't find the command; implies that
+                // the command was likely misspelt
+                let file = SimpleFile::new("", &command);
+                let diagnostic = &MisspelledBashCommand(editor).report();
+                term::emit(&mut WRITER.lock(), &CONFIG, &file, diagnostic).unwrap();
+                return 1;
+            }
+            _ => {
+                if !status.success() {
+                    panic!("Bash returned unsuccessful exit status: {:?}", status)
+                }
             }
         }
-    }
 
-    let file_targets = FileList::parse_reader(fs::File::open(TEMP_FILE).unwrap())
-        .expect("Temporary file should not be empty");
+        FileList::parse_reader(fs::File::open(TEMP_FILE).unwrap(), null_separated)
+            .expect("Temporary file should not be empty")
+    };
 
     let request = {
         match RenameRequest::new(file_origins, file_targets) {
@@ -116,7 +184,7 @@ fn real_main() -> i32 {
         }
     };
 
-    request.print_diffs();
+    request.print_diffs(&rename_options);
 
     if !Confirm::new()
         .with_prompt("Do you want to continue?")
@@ -128,5 +196,12 @@ fn real_main() -> i32 {
     }
 
     println!("Looks like you want to continue");
+
+    if let Err(err) = request.rename(&rename_options) {
+        let file = SimpleFile::new("", "");
+        term::emit(&mut WRITER.lock(), &CONFIG, &file, &err.report()).unwrap();
+        return 1;
+    }
+
     0
 }